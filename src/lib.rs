@@ -1,8 +1,12 @@
+extern crate noise;
 extern crate rand;
 
 use std::ops::{Add, Sub};
-use std::collections::HashMap;
-use rand::distributions::{IndependentSample, Range};
+use std::collections::{HashMap, HashSet};
+use rand::distributions::{Distribution, Uniform};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use noise::{NoiseFn, OpenSimplex, Seedable};
 
 
 #[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
@@ -49,10 +53,61 @@ fn neighbors(point: Point) -> Vec<Point> {
         .collect()
 }
 
+// The eight neighbor offsets in clockwise rotational order, used by
+// `region_outline` to walk a region's perimeter one boundary cell at a time.
+const CLOCKWISE_OFFSETS: [Point; 8] = [Point { x: 0, y: 1 },
+                                       Point { x: 1, y: 1 },
+                                       Point { x: 1, y: 0 },
+                                       Point { x: 1, y: -1 },
+                                       Point { x: 0, y: -1 },
+                                       Point { x: -1, y: -1 },
+                                       Point { x: -1, y: 0 },
+                                       Point { x: -1, y: 1 }];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    pub survive: [bool; 9],
+    pub birth: [bool; 9],
+}
+
+impl Rule {
+    pub fn conway() -> Self {
+        Rule::parse("B3/S23")
+    }
+
+    pub fn parse(rulestring: &str) -> Self {
+        let mut survive = [false; 9];
+        let mut birth = [false; 9];
+        let mut in_birth = true;
+
+        for c in rulestring.chars() {
+            match c {
+                'B' | 'b' => in_birth = true,
+                'S' | 's' | '/' => in_birth = false,
+                _ => {
+                    if let Some(count) = c.to_digit(10) {
+                        let count = count as usize;
+                        if count < 9 {
+                            if in_birth {
+                                birth[count] = true;
+                            } else {
+                                survive[count] = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Rule { survive, birth }
+    }
+}
+
 #[derive(Debug)]
 pub struct Grid {
     cells: HashMap<Point, u64>,
     generation: u64,
+    rule: Rule,
 }
 
 impl Grid {
@@ -60,6 +115,7 @@ impl Grid {
         Grid {
             cells: HashMap::new(),
             generation: 0,
+            rule: Rule::conway(),
         }
     }
 
@@ -74,26 +130,77 @@ impl Grid {
         Grid {
             cells,
             generation: 0,
+            rule: Rule::conway(),
         }
     }
 
+    pub fn set_rule(&mut self, rule: Rule) -> &mut Self {
+        self.rule = rule;
+        self
+    }
+
     pub fn random(top_left: Point, bottom_right: Point) -> Self {
-        let x_range = Range::new(top_left.x, bottom_right.x);
-        let y_range = Range::new(top_left.y, bottom_right.y);
+        let x_range = Uniform::from(top_left.x..bottom_right.x);
+        let y_range = Uniform::from(top_left.y..bottom_right.y);
         let desired_count = (bottom_right.x - top_left.x) * (bottom_right.y - top_left.y) * 8 / 10;
         let mut rng = rand::thread_rng();
         let mut grid = Grid::empty();
 
         for _ in 0..desired_count {
             grid.add_point(Point {
-                               x: x_range.ind_sample(&mut rng),
-                               y: y_range.ind_sample(&mut rng),
+                               x: x_range.sample(&mut rng),
+                               y: y_range.sample(&mut rng),
                            });
         }
 
         grid
     }
 
+    pub fn random_seeded(seed: u64,
+                         top_left: Point,
+                         bottom_right: Point,
+                         density: f64)
+                         -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut grid = Grid::empty();
+
+        for y in top_left.y..bottom_right.y {
+            for x in top_left.x..bottom_right.x {
+                if rng.gen::<f64>() < density {
+                    grid.add_point(Point { x, y });
+                }
+            }
+        }
+
+        grid
+    }
+
+    pub fn from_noise(seed: u64,
+                      top_left: Point,
+                      bottom_right: Point,
+                      scale: f64,
+                      threshold: f64)
+                      -> Self {
+        // OpenSimplex only accepts a 32-bit seed, so fold the high and low
+        // halves of the u64 together rather than truncating to the low word.
+        let folded_seed = (seed ^ (seed >> 32)) as u32;
+        let noise = OpenSimplex::new().set_seed(folded_seed);
+        let mut grid = Grid::empty();
+
+        for y in top_left.y..bottom_right.y {
+            for x in top_left.x..bottom_right.x {
+                let sample = noise.get([x as f64 * scale, y as f64 * scale]);
+                // OpenSimplex returns roughly [-1, 1]; remap into [0, 1].
+                let value = (sample + 1.0) / 2.0;
+                if value > threshold {
+                    grid.add_point(Point { x, y });
+                }
+            }
+        }
+
+        grid
+    }
+
     pub fn add_point(&mut self, point: Point) -> &mut Self {
         self.cells.entry(point).or_insert(self.generation);
         self
@@ -108,6 +215,39 @@ impl Grid {
         self.cells.get(point).map(|birth| self.generation - birth)
     }
 
+    pub fn find_regions(&self) -> Vec<Vec<Point>> {
+        let mut regions = Vec::new();
+        let mut visited: HashSet<Point> = HashSet::new();
+
+        for cell in self.cells.keys() {
+            if visited.contains(cell) {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            let mut stack = vec![*cell];
+            visited.insert(*cell);
+
+            while let Some(point) = stack.pop() {
+                region.push(point);
+                for neighbor in neighbors(point) {
+                    if self.cells.contains_key(&neighbor) && !visited.contains(&neighbor) {
+                        visited.insert(neighbor);
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            regions.push(region);
+        }
+
+        regions
+    }
+
+    pub fn region_outline(&self, region: &[Point]) -> Vec<Point> {
+        smooth_polygon(&trace_boundary(region))
+    }
+
     pub fn tick(&mut self) -> &mut Self {
         self.generation += 1;
         let mut next_generation = HashMap::new();
@@ -115,7 +255,7 @@ impl Grid {
         for (cell, generation) in &self.cells {
             let count = self.count_neighbors(cell);
 
-            if count > 1 && count < 4 {
+            if self.rule.survive[count] {
                 next_generation.insert(*cell, *generation);
             }
         }
@@ -123,7 +263,7 @@ impl Grid {
         for cell in self.dead_candidates() {
             let count = self.count_neighbors(&cell);
 
-            if count == 3 {
+            if self.rule.birth[count] {
                 next_generation.insert(cell, self.generation);
             }
         }
@@ -151,6 +291,79 @@ impl Grid {
     }
 }
 
+// Trace the perimeter of a region as an ordered, unsmoothed polygon using
+// Moore-neighbor boundary tracing, walking clockwise from the lowest-leftmost
+// cell.
+fn trace_boundary(region: &[Point]) -> Vec<Point> {
+    let live: HashSet<Point> = region.iter().cloned().collect();
+
+    let start = match region.iter().min_by_key(|p| (p.y, p.x)) {
+        Some(start) => *start,
+        None => return Vec::new(),
+    };
+
+    // We arrive at the start having come from the west, so begin scanning
+    // just past that direction in clockwise order.
+    const START_BACK: usize = 6; // index of the west offset in CLOCKWISE_OFFSETS
+
+    // Jacob's stopping criterion: a (cell, incoming-direction) state fully
+    // determines the rest of the walk, so the trace is complete once one
+    // repeats. Keying on the pair rather than on the cell alone keeps the far
+    // side of a concave region from being dropped when the perimeter brushes
+    // past an already-visited cell from a new direction.
+    let mut outline = Vec::new();
+    let mut visited: HashSet<(Point, usize)> = HashSet::new();
+    let mut current = start;
+    let mut back = START_BACK;
+
+    while visited.insert((current, back)) {
+        outline.push(current);
+
+        let mut found = None;
+        for step in 1..=8 {
+            let index = (back + step) % 8;
+            let candidate = current + CLOCKWISE_OFFSETS[index];
+            if live.contains(&candidate) {
+                found = Some((index, candidate));
+                break;
+            }
+        }
+
+        match found {
+            Some((index, candidate)) => {
+                current = candidate;
+                // Re-enter the next cell facing back toward the one we left.
+                back = (index + 4) % 8;
+            }
+            None => break, // isolated cell: no perimeter to trace
+        }
+    }
+
+    outline
+}
+
+// Smooth a boundary polygon by replacing each interior vertex with the average
+// of the five-point window centered on it, leaving the first and last two
+// vertices fixed.
+fn smooth_polygon(polygon: &[Point]) -> Vec<Point> {
+    if polygon.len() < 5 {
+        return polygon.to_vec();
+    }
+
+    let mut smoothed = polygon.to_vec();
+    for i in 2..polygon.len() - 2 {
+        let sum = polygon[i - 2] + polygon[i - 1] + polygon[i] + polygon[i + 1] + polygon[i + 2];
+        // Round to the nearest lattice point rather than truncating toward zero,
+        // which would barely move the vertex and bias negative coordinates.
+        smoothed[i] = Point {
+            x: (sum.x as f64 / 5.0).round() as i64,
+            y: (sum.y as f64 / 5.0).round() as i64,
+        };
+    }
+
+    smoothed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -313,6 +526,139 @@ mod grid_tests {
         assert_eq!(Some(1), g.age_of_point(&point));
     }
 
+    #[test]
+    fn find_regions_groups_connected_cells() {
+        let points = [Point { x: 0, y: 0 },
+                      Point { x: 1, y: 0 },
+                      Point { x: 1, y: 1 },
+                      Point { x: 10, y: 10 }];
+        let g = Grid::with_points(points.iter());
+        let mut regions = g.find_regions();
+        regions.sort_by_key(|region| region.len());
+
+        assert_eq!(2, regions.len());
+        assert_eq!(1, regions[0].len());
+        assert_eq!(3, regions[1].len());
+    }
+
+    #[test]
+    fn region_outline_of_a_single_cell_is_that_cell() {
+        let points = [Point { x: 4, y: 4 }];
+        let g = Grid::with_points(points.iter());
+        let region = g.find_regions().pop().unwrap();
+
+        assert_eq!(vec![Point { x: 4, y: 4 }], g.region_outline(&region));
+    }
+
+    #[test]
+    fn trace_boundary_visits_every_cell_of_a_small_block() {
+        let points = [Point { x: 0, y: 0 },
+                      Point { x: 1, y: 0 },
+                      Point { x: 0, y: 1 },
+                      Point { x: 1, y: 1 }];
+        let outline = trace_boundary(&points);
+
+        for point in points.iter() {
+            assert!(outline.contains(point));
+        }
+    }
+
+    #[test]
+    fn trace_boundary_walks_a_concave_u_shape_completely() {
+        // A U open at the top: two arms joined by a bottom row, with a gap at
+        // (1, 1). The walk reaches the bottom-middle cell (1, 0) twice, from
+        // different directions, and must still cover every cell of both arms.
+        let points = [Point { x: 0, y: 1 },
+                      Point { x: 2, y: 1 },
+                      Point { x: 0, y: 0 },
+                      Point { x: 1, y: 0 },
+                      Point { x: 2, y: 0 }];
+        let outline = trace_boundary(&points);
+
+        for point in points.iter() {
+            assert!(outline.contains(point), "outline missing {:?}", point);
+        }
+    }
+
+    #[test]
+    fn smooth_polygon_moves_interior_vertices() {
+        let polygon = [Point { x: 0, y: 0 },
+                       Point { x: 0, y: 0 },
+                       Point { x: 10, y: 0 },
+                       Point { x: 0, y: 0 },
+                       Point { x: 0, y: 0 }];
+        let smoothed = smooth_polygon(&polygon);
+
+        assert_eq!(Point { x: 2, y: 0 }, smoothed[2]);
+        assert!(smoothed[2] != polygon[2]);
+        // Endpoints stay fixed.
+        assert_eq!(polygon[0], smoothed[0]);
+        assert_eq!(polygon[4], smoothed[4]);
+    }
+
+    #[test]
+    fn random_seeded_is_reproducible() {
+        let top_left = Point { x: 0, y: 0 };
+        let bottom_right = Point { x: 16, y: 16 };
+        let a = Grid::random_seeded(42, top_left, bottom_right, 0.5);
+        let b = Grid::random_seeded(42, top_left, bottom_right, 0.5);
+
+        assert_eq!(a.cells, b.cells);
+    }
+
+    #[test]
+    fn random_seeded_differs_between_seeds() {
+        let top_left = Point { x: 0, y: 0 };
+        let bottom_right = Point { x: 16, y: 16 };
+        let a = Grid::random_seeded(1, top_left, bottom_right, 0.5);
+        let b = Grid::random_seeded(2, top_left, bottom_right, 0.5);
+
+        assert!(a.cells != b.cells);
+    }
+
+    #[test]
+    fn from_noise_is_reproducible() {
+        let top_left = Point { x: 0, y: 0 };
+        let bottom_right = Point { x: 16, y: 16 };
+        let a = Grid::from_noise(7, top_left, bottom_right, 0.1, 0.5);
+        let b = Grid::from_noise(7, top_left, bottom_right, 0.1, 0.5);
+
+        assert_eq!(a.cells, b.cells);
+    }
+
+    #[test]
+    fn conway_rule_matches_the_b3_s23_rulestring() {
+        let rule = Rule::conway();
+        assert_eq!([false, false, true, true, false, false, false, false, false],
+                   rule.survive);
+        assert_eq!([false, false, false, true, false, false, false, false, false],
+                   rule.birth);
+    }
+
+    #[test]
+    fn parse_reads_birth_and_survival_counts() {
+        let rule = Rule::parse("B45678/S5678");
+        assert!(rule.birth[4] && rule.birth[5] && rule.birth[6] && rule.birth[7] && rule.birth[8]);
+        assert!(!rule.birth[3]);
+        assert!(rule.survive[5] && rule.survive[6] && rule.survive[7] && rule.survive[8]);
+        assert!(!rule.survive[4]);
+    }
+
+    #[test]
+    fn tick_honors_a_custom_rule() {
+        let points = [Point { x: 0, y: 0 },
+                      Point { x: 1, y: 0 },
+                      Point { x: 0, y: 1 }];
+        let mut g = Grid::with_points(points.iter());
+        g.set_rule(Rule::parse("B/S012345678"));
+        g.tick();
+
+        for point in points.iter() {
+            assert!(g.cells.contains_key(point));
+        }
+        assert!(!g.cells.contains_key(&Point { x: 1, y: 1 }));
+    }
+
     #[test]
     fn remove_point_removes_a_point() {
         let point = Point { x: 0, y: 0 };
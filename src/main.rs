@@ -56,11 +56,93 @@ impl<'a> Iterator for ViewIterator<'a> {
     }
 }
 
+#[derive(Clone, Debug)]
+enum Operation {
+    Add(Point),
+    Remove(Point),
+    Stroke(Vec<Operation>),
+}
+
+impl Operation {
+    fn apply(&self, grid: &mut Grid) {
+        match *self {
+            Operation::Add(point) => {
+                grid.add_point(point);
+            }
+            Operation::Remove(ref point) => {
+                grid.remove_point(point);
+            }
+            Operation::Stroke(ref operations) => {
+                for operation in operations {
+                    operation.apply(grid);
+                }
+            }
+        }
+    }
+
+    fn invert(&self) -> Operation {
+        match *self {
+            Operation::Add(point) => Operation::Remove(point),
+            Operation::Remove(point) => Operation::Add(point),
+            Operation::Stroke(ref operations) => {
+                Operation::Stroke(operations.iter().rev().map(|op| op.invert()).collect())
+            }
+        }
+    }
+}
+
+struct UndoStack {
+    undo: Vec<Operation>,
+    redo: Vec<Operation>,
+}
+
+impl UndoStack {
+    fn new() -> Self {
+        UndoStack {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, operation: Operation) {
+        self.undo.push(operation);
+        self.redo.clear();
+    }
+
+    fn undo(&mut self, grid: &mut Grid) {
+        if let Some(operation) = self.undo.pop() {
+            operation.invert().apply(grid);
+            self.redo.push(operation);
+        }
+    }
+
+    fn redo(&mut self, grid: &mut Grid) {
+        if let Some(operation) = self.redo.pop() {
+            operation.apply(grid);
+            self.undo.push(operation);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Mode {
+    Pan,
+    Paint,
+}
+
+// Rate of the exponential-decay lerp the camera uses to chase its target; larger
+// values snap faster.
+const CAMERA_DECAY: f64 = 12.0;
+
 struct App {
     gl: GlGraphics,
     grid: Grid,
-    view: View,
-    point_width: f64,
+    offset_x: f64,
+    offset_y: f64,
+    scale: f64,
+    target_offset_x: f64,
+    target_offset_y: f64,
+    target_scale: f64,
     elapsed: f64,
     generation: f64,
     rate: f64,
@@ -68,25 +150,34 @@ struct App {
     window_width: u32,
     window_height: u32,
     cursor: Option<Point>,
+    undo_stack: UndoStack,
+    stroke: Option<Vec<Operation>>,
+    ctrl_down: bool,
+    mode: Mode,
+    brush_size: i64,
+    erasing: bool,
+    paused: bool,
 }
 
 impl App {
     fn new(open_gl: piston_window::OpenGL, window_width: u32, window_height: u32) -> Self {
-        let point_width = 10.0;
+        let scale = 10.0;
 
-        let view = View {
-            top_left: Point { x: 0, y: 0 },
-            bottom_right: Point {
-                x: (window_width as f64 / point_width) as i64,
-                y: (window_height as f64 / point_width) as i64,
-            },
+        let top_left = Point { x: 0, y: 0 };
+        let bottom_right = Point {
+            x: (window_width as f64 / scale) as i64,
+            y: (window_height as f64 / scale) as i64,
         };
 
         App {
             gl: GlGraphics::new(open_gl),
-            grid: Grid::random(view.top_left, view.bottom_right),
-            point_width: point_width,
-            view: view,
+            grid: Grid::random(top_left, bottom_right),
+            offset_x: 0.0,
+            offset_y: 0.0,
+            scale: scale,
+            target_offset_x: 0.0,
+            target_offset_y: 0.0,
+            target_scale: scale,
             elapsed: 0.0,
             generation: 0.0,
             rate: 10.0,
@@ -94,18 +185,192 @@ impl App {
             window_width: window_width,
             window_height: window_height,
             cursor: None,
+            undo_stack: UndoStack::new(),
+            stroke: None,
+            ctrl_down: false,
+            mode: Mode::Pan,
+            brush_size: 1,
+            erasing: false,
+            paused: false,
+        }
+    }
+
+    // The integer cell window currently visible, derived from the interpolated
+    // camera; cell logic stays on the integer lattice.
+    fn view(&self) -> View {
+        let top_left = Point {
+            x: self.offset_x.floor() as i64,
+            y: self.offset_y.floor() as i64,
+        };
+        View {
+            top_left: top_left,
+            bottom_right: Point {
+                x: top_left.x + (self.window_width as f64 / self.scale).ceil() as i64 + 1,
+                y: top_left.y + (self.window_height as f64 / self.scale).ceil() as i64 + 1,
+            },
+        }
+    }
+
+    // Translate a pixel position from the window into the cell it sits over.
+    fn point_at(&self, px: f64, py: f64) -> Point {
+        Point {
+            x: (self.offset_x + px / self.scale).floor() as i64,
+            y: (self.offset_y + py / self.scale).floor() as i64,
+        }
+    }
+
+    fn set_cursor(&mut self, px: f64, py: f64) {
+        self.cursor = Some(self.point_at(px, py));
+        if self.mode == Mode::Paint && self.mouse_down {
+            self.paint();
+        }
+    }
+
+    // The cells covered by the brush: a disc of `brush_size` radius around a center.
+    fn brush_footprint(&self, center: Point) -> Vec<Point> {
+        let radius = self.brush_size;
+        let mut points = Vec::new();
+        for dy in -radius..radius + 1 {
+            for dx in -radius..radius + 1 {
+                if dx * dx + dy * dy <= radius * radius {
+                    points.push(center + Point { x: dx, y: dy });
+                }
+            }
+        }
+
+        points
+    }
+
+    // Begin a paint stroke, choosing add-vs-erase from the cell under the cursor
+    // so a drag that starts on a live cell erases and one that starts on empty
+    // space paints.
+    fn begin_paint(&mut self) {
+        if let Some(center) = self.cursor {
+            self.erasing = self.grid.age_of_point(&center).is_some();
+            self.begin_stroke();
+            self.paint();
+        }
+    }
+
+    // Apply the brush at the current cursor, recording only the cells that
+    // actually change state so the stroke inverts cleanly.
+    fn paint(&mut self) {
+        let center = match self.cursor {
+            Some(center) => center,
+            None => return,
+        };
+
+        for point in self.brush_footprint(center) {
+            let alive = self.grid.age_of_point(&point).is_some();
+            if self.erasing && alive {
+                self.record(Operation::Remove(point));
+            } else if !self.erasing && !alive {
+                self.record(Operation::Add(point));
+            }
+        }
+    }
+
+    fn grow_brush(&mut self) {
+        const UPPER_BOUND: i64 = 32;
+        self.brush_size = UPPER_BOUND.min(self.brush_size + 1);
+    }
+
+    fn shrink_brush(&mut self) {
+        self.brush_size = 1.max(self.brush_size - 1);
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.report();
+    }
+
+    // Advance a single generation while paused, for frame-by-frame inspection.
+    fn step(&mut self) {
+        if self.paused {
+            self.grid.tick();
+            self.generation += 1.0;
+            self.report();
+        }
+    }
+
+    fn faster(&mut self) {
+        const UPPER_BOUND: f64 = 60.0;
+        self.rate = UPPER_BOUND.min(self.rate * 1.5);
+        self.report();
+    }
+
+    fn slower(&mut self) {
+        const LOWER_BOUND: f64 = 1.0;
+        self.rate = LOWER_BOUND.max(self.rate / 1.5);
+        self.report();
+    }
+
+    // Surface playback state so users can tune speed without recompiling.
+    fn report(&self) {
+        println!("generation {} | rate {:.1}/s | {}",
+                 self.generation as u64,
+                 self.rate,
+                 if self.paused { "paused" } else { "running" });
+    }
+
+    fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            Mode::Pan => Mode::Paint,
+            Mode::Paint => Mode::Pan,
+        };
+    }
+
+    // Begin recording a continuous drag so the whole painted line collapses
+    // into a single undo step.
+    fn begin_stroke(&mut self) {
+        self.stroke = Some(Vec::new());
+    }
+
+    // Record one edit, applying it immediately and appending it to the active
+    // stroke if one is open, otherwise pushing it as a standalone operation.
+    fn record(&mut self, operation: Operation) {
+        operation.apply(&mut self.grid);
+        match self.stroke {
+            Some(ref mut operations) => operations.push(operation),
+            None => self.undo_stack.push(operation),
+        }
+    }
+
+    // Close an open stroke, pushing it as one operation when it painted anything.
+    fn end_stroke(&mut self) {
+        if let Some(operations) = self.stroke.take() {
+            if !operations.is_empty() {
+                self.undo_stack.push(Operation::Stroke(operations));
+            }
         }
     }
 
+    fn undo(&mut self) {
+        self.undo_stack.undo(&mut self.grid);
+    }
+
+    fn redo(&mut self) {
+        self.undo_stack.redo(&mut self.grid);
+    }
+
     fn render(&mut self, args: &RenderArgs) {
         use graphics::*;
         const WHITE: [f32; 4] = [1.0; 4];
 
-        let width = self.point_width;
+        const BRUSH: [f32; 4] = [0.2, 0.4, 0.9, 0.25];
+
+        let width = self.scale;
         let square = rectangle::square(0.0, 0.0, width);
-        let base_point = self.view.top_left;
-        let view_iter = self.view.into_iter();
+        let offset_x = self.offset_x;
+        let offset_y = self.offset_y;
+        let view = self.view();
+        let view_iter = view.into_iter();
         let grid: &Grid = &self.grid;
+        let brush = if self.mode == Mode::Paint {
+            self.cursor.map(|center| self.brush_footprint(center))
+        } else {
+            None
+        };
 
         self.gl
             .draw(args.viewport(), move |c, gl| {
@@ -114,8 +379,8 @@ impl App {
 
                 for point in view_iter {
                     if let Some(age) = grid.age_of_point(&point) {
-                        let x = (point.x - base_point.x) as f64;
-                        let y = (point.y - base_point.y) as f64;
+                        let x = point.x as f64 - offset_x;
+                        let y = point.y as f64 - offset_y;
                         let transform = c.transform.trans(x * width, y * width);
                         let shade_adjustment = 0.01 * age as f32;
                         let color = [0.0, 0.0, 0.0, 0.15 + shade_adjustment];
@@ -123,17 +388,39 @@ impl App {
                         rectangle(color, square, transform, gl);
                     }
                 }
+
+                if let Some(footprint) = brush {
+                    for point in footprint {
+                        let x = point.x as f64 - offset_x;
+                        let y = point.y as f64 - offset_y;
+                        let transform = c.transform.trans(x * width, y * width);
+                        rectangle(BRUSH, square, transform, gl);
+                    }
+                }
             });
     }
 
     fn update(&mut self, args: &UpdateArgs) {
-        if self.mouse_down {
+        // Ease the camera toward its target independently of the simulation so
+        // panning and zooming stay smooth even while paused.
+        let blend = 1.0 - (-CAMERA_DECAY * args.dt).exp();
+        self.offset_x += (self.target_offset_x - self.offset_x) * blend;
+        self.offset_y += (self.target_offset_y - self.offset_y) * blend;
+        self.scale += (self.target_scale - self.scale) * blend;
+
+        if self.mouse_down || self.paused {
             return;
         }
+        // Schedule on a per-tick interval (time since the last tick) rather than
+        // an absolute generation/rate threshold, so live `rate` changes take
+        // effect from the next tick instead of retroactively rescaling all the
+        // elapsed time.
         self.elapsed += args.dt;
-        if self.elapsed > self.generation / self.rate {
+        let interval = 1.0 / self.rate;
+        while self.elapsed >= interval {
             self.grid.tick();
             self.generation += 1.0;
+            self.elapsed -= interval;
         }
     }
 
@@ -142,33 +429,19 @@ impl App {
         const LOWER_BOUND: f64 = 1.0;
 
         if adjustment > 0.0 {
-            self.point_width = UPPER_BOUND.min(self.point_width * 1.5);
+            self.target_scale = UPPER_BOUND.min(self.target_scale * 1.5);
         } else {
-            self.point_width = LOWER_BOUND.max(self.point_width / 1.5);
+            self.target_scale = LOWER_BOUND.max(self.target_scale / 1.5);
         }
-
-        self.view = View {
-            top_left: Point { x: 0, y: 0 },
-            bottom_right: Point {
-                x: (self.window_width as f64 / self.point_width) as i64,
-                y: (self.window_height as f64 / self.point_width) as i64,
-            },
-        };
     }
 
     fn shift(&mut self, dx: f64, dy: f64) {
-        if !self.mouse_down {
+        if !self.mouse_down || self.mode != Mode::Pan {
             return;
         }
-        let adjustment = Point {
-            x: -dx as i64,
-            y: -dy as i64,
-        };
 
-        self.view = View {
-            top_left: self.view.top_left + adjustment,
-            bottom_right: self.view.bottom_right + adjustment,
-        };
+        self.target_offset_x -= dx / self.scale;
+        self.target_offset_y -= dy / self.scale;
     }
 }
 
@@ -191,16 +464,47 @@ fn main() {
         e.update(|u| app.update(u));
         e.mouse_scroll(|_dx, dy| app.zoom(dy));
         e.mouse_relative(|dx, dy| app.shift(dx, dy));
+        e.mouse_cursor(|x, y| app.set_cursor(x, y));
 
         if let Some(button) = e.press_args() {
-            if let Button::Mouse(_button) = button {
-                app.mouse_down = true;
+            match button {
+                Button::Mouse(_button) => {
+                    app.mouse_down = true;
+                    if app.mode == Mode::Paint {
+                        app.begin_paint();
+                    }
+                }
+                Button::Keyboard(key) => {
+                    match key {
+                        Key::LCtrl | Key::RCtrl => app.ctrl_down = true,
+                        Key::Z if app.ctrl_down => app.undo(),
+                        Key::Y if app.ctrl_down => app.redo(),
+                        Key::M => app.toggle_mode(),
+                        Key::RightBracket => app.grow_brush(),
+                        Key::LeftBracket => app.shrink_brush(),
+                        Key::Space => app.toggle_pause(),
+                        Key::N => app.step(),
+                        Key::Up => app.faster(),
+                        Key::Down => app.slower(),
+                        _ => {}
+                    }
+                }
+                _ => {}
             }
         }
 
         if let Some(button) = e.release_args() {
-            if let Button::Mouse(_button) = button {
-                app.mouse_down = false;
+            match button {
+                Button::Mouse(_button) => {
+                    app.mouse_down = false;
+                    app.end_stroke();
+                }
+                Button::Keyboard(key) => {
+                    if let Key::LCtrl | Key::RCtrl = key {
+                        app.ctrl_down = false;
+                    }
+                }
+                _ => {}
             }
         }
     }